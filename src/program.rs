@@ -1,6 +1,7 @@
 use gl;
+use libc;
 use std::{fmt, mem, ptr};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use {Display, DisplayImpl, GlObject};
 
@@ -28,7 +29,10 @@ pub struct Program {
     id: gl::types::GLuint,
 
     // location, type and size of each uniform, ordered by name
-    uniforms: Arc<HashMap<String, (gl::types::GLint, gl::types::GLenum, gl::types::GLint)>>
+    uniforms: Arc<HashMap<String, (gl::types::GLint, gl::types::GLenum, gl::types::GLint)>>,
+
+    // location, type and size of each vertex attribute, keyed by name
+    attributes: Arc<HashMap<String, (gl::types::GLint, gl::types::GLenum, gl::types::GLint)>>
 }
 
 /// Error that can be triggered when creating a `Program`.
@@ -47,6 +51,202 @@ pub enum ProgramCreationError {
     ///
     /// Usually the case of geometry shaders.
     ShaderTypeNotSupported,
+
+    /// Error while resolving a `#include` directive: the path couldn't be resolved, or an
+    /// include cycle was detected.
+    IncludeError(String),
+}
+
+/// GLSL version/profile a shader source should be compiled against.
+///
+/// When set, glium prepends the matching `#version` directive to each shader's source before
+/// handing it to the driver. This lets a single source tree target both desktop and ES
+/// backends without the caller hand-writing the directive.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum ShaderVersion {
+    /// OpenGL 3.3, core profile. Emits `#version 330 core`.
+    Glsl330Core,
+    /// OpenGL ES 2.0. Emits `#version 100` plus a `GLES2_RENDERER` define.
+    Gles2,
+    /// OpenGL ES 3.0. Emits `#version 300 es`.
+    Gles3,
+}
+
+impl ShaderVersion {
+    /// Returns the `#version` header line(s) for this target.
+    fn header(&self) -> &'static str {
+        match *self {
+            ShaderVersion::Glsl330Core => "#version 330 core\n",
+            ShaderVersion::Gles2 => "#version 100\n#define GLES2_RENDERER\n",
+            ShaderVersion::Gles3 => "#version 300 es\n",
+        }
+    }
+}
+
+/// Where the contents of a `#include`d shader file come from.
+///
+/// Passed to `ProgramBuilder::include_resolver` or `Program::new` to give glium a portable
+/// module system for GLSL, without relying on the non-universally-supported
+/// `GL_ARB_shading_language_include` extension.
+pub enum IncludeSource {
+    /// A fixed table mapping a virtual path to the file's contents.
+    Files(HashMap<String, String>),
+    /// A closure invoked with each requested path, returning its contents if known.
+    Resolver(Box<FnMut(&str) -> Option<String> + 'static>),
+}
+
+impl IncludeSource {
+    /// Resolves a single `#include` path, if this source knows about it.
+    fn resolve(&mut self, path: &str) -> Option<String> {
+        match *self {
+            IncludeSource::Files(ref map) => map.get(path).map(|s| s.clone()),
+            IncludeSource::Resolver(ref mut resolver) => (*resolver)(path),
+        }
+    }
+}
+
+/// Expands every `#include "path"` directive found in `source`, recursively resolving
+/// nested includes through `source_resolver`.
+///
+/// `#include` occurrences inside a `/* */` block comment or after a `//` are left alone.
+/// A path that is still being resolved higher up the include chain is reported as an
+/// `IncludeError` instead of recursing forever, and a path that was already expanded
+/// earlier in this compilation unit is skipped the second time it's encountered.
+fn preprocess_includes(source_resolver: &mut IncludeSource, source: &str)
+    -> Result<String, ProgramCreationError>
+{
+    let mut stack = HashSet::new();
+    let mut included = HashSet::new();
+    expand_includes(source_resolver, source, &mut stack, &mut included)
+}
+
+fn expand_includes(source_resolver: &mut IncludeSource, source: &str, stack: &mut HashSet<String>,
+                    included: &mut HashSet<String>) -> Result<String, ProgramCreationError>
+{
+    let mut in_block_comment = false;
+    let mut output = String::new();
+
+    for line in source.lines() {
+        let (code, still_in_comment) = strip_comments(line, in_block_comment);
+        in_block_comment = still_in_comment;
+
+        match parse_include_directive(code.as_slice()) {
+            Some(path) => {
+                if included.contains(&path) {
+                    continue;
+                }
+
+                if !stack.insert(path.clone()) {
+                    return Err(ProgramCreationError::IncludeError(
+                        format!("include cycle detected on \"{}\"", path)));
+                }
+
+                let contents = match source_resolver.resolve(path.as_slice()) {
+                    Some(contents) => contents,
+                    None => return Err(ProgramCreationError::IncludeError(
+                        format!("could not resolve #include \"{}\"", path))),
+                };
+
+                let expanded = try!(expand_includes(source_resolver, contents.as_slice(), stack,
+                                                      included));
+                output.push_str(expanded.as_slice());
+                output.push_str("\n");
+
+                stack.remove(&path);
+                included.insert(path);
+            },
+            None => {
+                output.push_str(line);
+                output.push_str("\n");
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Removes `//` and `/* */` comments from a single line, given whether a block comment was
+/// already open coming into it. Returns the remaining code and whether a block comment is
+/// still open at the end of the line.
+fn strip_comments(line: &str, mut in_block_comment: bool) -> (String, bool) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if in_block_comment {
+            if chars[i] == '*' && i + 1 < chars.len() && chars[i + 1] == '/' {
+                in_block_comment = false;
+                i += 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+
+        if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '/' {
+            break;
+        }
+
+        if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '*' {
+            in_block_comment = true;
+            i += 2;
+            continue;
+        }
+
+        result.push(chars[i]);
+        i += 1;
+    }
+
+    (result, in_block_comment)
+}
+
+/// Parses a `#include "path"` directive out of an already comment-stripped line.
+///
+/// Whitespace is allowed between the `#` and `include`, matching the C/GLSL preprocessor
+/// grammar (e.g. `#  include "foo.glsl"`).
+fn parse_include_directive(code: &str) -> Option<String> {
+    let trimmed = code.trim();
+    if !trimmed.starts_with('#') {
+        return None;
+    }
+
+    let rest = trimmed[1..].trim_left();
+    if !rest.starts_with("include") {
+        return None;
+    }
+
+    let rest = rest[7..].trim_left();
+    if !rest.starts_with('"') {
+        return None;
+    }
+
+    match rest[1..].find('"') {
+        Some(end) => Some(rest[1..1 + end].to_string()),
+        None => None
+    }
+}
+
+/// Prepends the `#version` directive and any `#define`s to a shader's source code.
+///
+/// GLSL requires the `#version` directive to be the first thing in the source, so the header
+/// is built and concatenated in front of the caller's code rather than supplied separately.
+fn apply_shader_header(version: Option<ShaderVersion>, defines: &[(&str, &str)],
+                        source_code: &str) -> String
+{
+    let mut header = String::new();
+
+    match version {
+        Some(v) => header.push_str(v.header()),
+        None => ()
+    }
+
+    for &(name, value) in defines.iter() {
+        header.push_str(format!("#define {} {}\n", name, value).as_slice());
+    }
+
+    header.push_str(source_code);
+    header
 }
 
 impl Program {
@@ -66,26 +266,62 @@ impl Program {
     /// # let display: glium::Display = unsafe { std::mem::uninitialized() };
     /// # let vertex_source = ""; let fragment_source = ""; let geometry_source = "";
     /// let program = glium::Program::new(&display, vertex_source, fragment_source,
-    ///     Some(geometry_source));
+    ///     Some(geometry_source), None, &[], None);
     /// ```
-    /// 
+    ///
+    /// `version` and `defines` let the same source serve multiple backends: when `version` is
+    /// `Some`, the matching `#version` directive is emitted as the very first line of every
+    /// shader stage, followed by one `#define NAME VALUE` per entry in `defines`.
+    ///
+    /// For anything beyond this vertex/geometry/fragment combination (tessellation, compute,
+    /// or a compute-only pipeline), use `ProgramBuilder` instead.
+    ///
+    /// `include_resolver`, when set, is used to expand `#include "path"` directives found in
+    /// any of the shader sources before they are compiled. See `ProgramBuilder::include_resolver`
+    /// for details.
+    ///
     #[experimental = "The list of shaders and the result error will probably change"]
     pub fn new(display: &Display, vertex_shader: &str, fragment_shader: &str,
-               geometry_shader: Option<&str>) -> Result<Program, ProgramCreationError>
+               geometry_shader: Option<&str>, version: Option<ShaderVersion>,
+               defines: &[(&str, &str)], include_resolver: Option<IncludeSource>)
+        -> Result<Program, ProgramCreationError>
     {
-        let mut shaders_store = Vec::new();
-        shaders_store.push(try!(build_shader(display, gl::VERTEX_SHADER, vertex_shader)));
-        match geometry_shader {
-            Some(gs) => shaders_store.push(try!(build_shader(display, gl::GEOMETRY_SHADER, gs))),
-            None => ()
-        }
-        shaders_store.push(try!(build_shader(display, gl::FRAGMENT_SHADER, fragment_shader)));
+        let mut builder = ProgramBuilder::new().vertex_shader(vertex_shader)
+                                                .fragment_shader(fragment_shader);
 
-        let mut shaders_ids = Vec::new();
-        for sh in shaders_store.iter() {
-            shaders_ids.push(sh.id);
+        builder = match geometry_shader {
+            Some(gs) => builder.geometry_shader(gs),
+            None => builder
+        };
+
+        builder = match version {
+            Some(v) => builder.version(v),
+            None => builder
+        };
+
+        for &(name, value) in defines.iter() {
+            builder = builder.define(name, value);
         }
 
+        builder = match include_resolver {
+            Some(r) => builder.include_resolver(r),
+            None => builder
+        };
+
+        builder.build(display)
+    }
+
+    /// Loads a program from a binary blob previously returned by `get_binary`.
+    ///
+    /// Skipping recompilation noticeably speeds up startup when an application links many
+    /// shaders. Drivers are free to reject a binary produced by a different driver version (the
+    /// most common case being a driver update between runs), in which case a `LinkingError` is
+    /// returned so the caller can fall back to compiling from source.
+    pub fn from_binary(display: &Display, format: gl::types::GLenum, binary: &[u8])
+        -> Result<Program, ProgramCreationError>
+    {
+        let binary = binary.to_vec();
+
         let (tx, rx) = channel();
         display.context.context.exec(proc(ctxt) {
             unsafe {
@@ -95,49 +331,19 @@ impl Program {
                     return;
                 }
 
-                // attaching shaders
-                for sh in shaders_ids.iter() {
-                    ctxt.gl.AttachShader(id, sh.clone());
-                }
+                ctxt.gl.ProgramBinary(id, format, binary.as_ptr() as *const libc::c_void,
+                    binary.len() as gl::types::GLsizei);
 
-                // linking and checking for errors
-                ctxt.gl.LinkProgram(id);
-                {   let mut link_success: gl::types::GLint = mem::uninitialized();
-                    ctxt.gl.GetProgramiv(id, gl::LINK_STATUS, &mut link_success);
-                    if link_success == 0 {
-                        use ProgramCreationError::LinkingError;
-
-                        match ctxt.gl.GetError() {
-                            gl::NO_ERROR => (),
-                            gl::INVALID_VALUE => {
-                                tx.send(Err(LinkingError(format!("glLinkProgram triggered \
-                                                                  GL_INVALID_VALUE"))));
-                                return;
-                            },
-                            gl::INVALID_OPERATION => {
-                                tx.send(Err(LinkingError(format!("glLinkProgram triggered \
-                                                                  GL_INVALID_OPERATION"))));
-                                return;
-                            },
-                            _ => {
-                                tx.send(Err(LinkingError(format!("glLinkProgram triggered an \
-                                                                  unknown error"))));
-                                return;
-                            }
-                        };
-
-                        let mut error_log_size: gl::types::GLint = mem::uninitialized();
-                        ctxt.gl.GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut error_log_size);
-
-                        let mut error_log: Vec<u8> = Vec::with_capacity(error_log_size as uint);
-                        ctxt.gl.GetProgramInfoLog(id, error_log_size, &mut error_log_size,
-                            error_log.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
-                        error_log.set_len(error_log_size as uint);
-
-                        let msg = String::from_utf8(error_log).unwrap();
-                        tx.send(Err(LinkingError(msg)));
-                        return;
-                    }
+                let mut link_success: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetProgramiv(id, gl::LINK_STATUS, &mut link_success);
+                if link_success == 0 {
+                    use ProgramCreationError::LinkingError;
+
+                    tx.send(Err(LinkingError(format!("glProgramBinary produced a program that \
+                                                      failed to link; the cached binary is \
+                                                      probably incompatible with the current \
+                                                      driver"))));
+                    return;
                 }
 
                 tx.send(Ok(id));
@@ -146,43 +352,357 @@ impl Program {
 
         let id = try!(rx.recv());
 
+        let (uniforms, attributes) = reflect_program(display, id);
+
+        Ok(Program {
+            display: display.context.clone(),
+            shaders: Vec::new(),
+            id: id,
+            uniforms: uniforms,
+            attributes: attributes,
+        })
+    }
+
+    /// Retrieves the binary representation of the linked program, if the driver supports it.
+    ///
+    /// The returned format enum must be passed back to `from_binary` together with the bytes
+    /// to reload the program without recompiling. Returns `None` if the driver is unable to
+    /// retrieve a binary for this program.
+    pub fn get_binary(&self) -> Option<(gl::types::GLenum, Vec<u8>)> {
+        let id = self.id;
+
         let (tx, rx) = channel();
-        display.context.context.exec(proc(ctxt) {
+        self.display.context.exec(proc(ctxt) {
             unsafe {
-                // reflecting program uniforms
-                let mut uniforms = HashMap::new();
+                let mut binary_len: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetProgramiv(id, gl::PROGRAM_BINARY_LENGTH, &mut binary_len);
+
+                if binary_len <= 0 {
+                    tx.send(None);
+                    return;
+                }
+
+                let mut binary: Vec<u8> = Vec::with_capacity(binary_len as uint);
+                let mut format: gl::types::GLenum = mem::uninitialized();
+                let mut written_len: gl::types::GLsizei = mem::uninitialized();
+                ctxt.gl.GetProgramBinary(id, binary_len, &mut written_len, &mut format,
+                    binary.as_mut_slice().as_mut_ptr() as *mut libc::c_void);
+                binary.set_len(written_len as uint);
 
-                let mut active_uniforms: gl::types::GLint = mem::uninitialized();
-                ctxt.gl.GetProgramiv(id, gl::ACTIVE_UNIFORMS, &mut active_uniforms);
+                tx.send(Some((format, binary)));
+            }
+        });
 
-                for uniform_id in range(0, active_uniforms) {
-                    let mut uniform_name_tmp: Vec<u8> = Vec::with_capacity(64);
-                    let mut uniform_name_tmp_len = 63;
+        rx.recv()
+    }
+}
 
-                    let mut data_type: gl::types::GLenum = mem::uninitialized();
-                    let mut data_size: gl::types::GLint = mem::uninitialized();
-                    ctxt.gl.GetActiveUniform(id, uniform_id as gl::types::GLuint, uniform_name_tmp_len,
-                        &mut uniform_name_tmp_len, &mut data_size, &mut data_type,
-                        uniform_name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
-                    uniform_name_tmp.set_len(uniform_name_tmp_len as uint);
+/// Builder used to create a `Program` out of an arbitrary combination of shader stages.
+///
+/// Unlike `Program::new`, which only supports the classic vertex/geometry/fragment
+/// combination, a `ProgramBuilder` accepts any subset of the GLSL pipeline stages, including
+/// tessellation and compute. Stages are supplied through the chained `*_shader` methods and
+/// the program is linked by calling `build`.
+///
+/// # Example
+///
+/// ```no_run
+/// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+/// # let vertex_source = ""; let fragment_source = "";
+/// let program = glium::ProgramBuilder::new()
+///     .vertex_shader(vertex_source)
+///     .fragment_shader(fragment_source)
+///     .build(&display);
+/// ```
+pub struct ProgramBuilder {
+    vertex_shader: Option<String>,
+    tess_control_shader: Option<String>,
+    tess_evaluation_shader: Option<String>,
+    geometry_shader: Option<String>,
+    fragment_shader: Option<String>,
+    compute_shader: Option<String>,
+    version: Option<ShaderVersion>,
+    defines: Vec<(String, String)>,
+    include_resolver: Option<IncludeSource>,
+}
 
-                    let uniform_name = String::from_utf8(uniform_name_tmp).unwrap();
-                    let location = ctxt.gl.GetUniformLocation(id, uniform_name.to_c_str().into_inner());
+impl ProgramBuilder {
+    /// Starts building a program with no stages set.
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder {
+            vertex_shader: None,
+            tess_control_shader: None,
+            tess_evaluation_shader: None,
+            geometry_shader: None,
+            fragment_shader: None,
+            compute_shader: None,
+            version: None,
+            defines: Vec::new(),
+            include_resolver: None,
+        }
+    }
 
-                    uniforms.insert(uniform_name, (location, data_type, data_size));
+    /// Sets the source code of the vertex shader.
+    pub fn vertex_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.vertex_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the source code of the tessellation control shader.
+    pub fn tess_control_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.tess_control_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the source code of the tessellation evaluation shader.
+    pub fn tess_evaluation_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.tess_evaluation_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the source code of the geometry shader.
+    pub fn geometry_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.geometry_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the source code of the fragment shader.
+    pub fn fragment_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.fragment_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the source code of the compute shader.
+    ///
+    /// A compute shader can be the only stage of a program; it is linked and reflected just
+    /// like a graphics pipeline would be.
+    pub fn compute_shader(mut self, source_code: &str) -> ProgramBuilder {
+        self.compute_shader = Some(source_code.to_string());
+        self
+    }
+
+    /// Sets the GLSL version/profile to target.
+    ///
+    /// The matching `#version` directive is emitted as the very first line of every shader
+    /// stage, since GLSL requires it to precede everything else in the source.
+    pub fn version(mut self, version: ShaderVersion) -> ProgramBuilder {
+        self.version = Some(version);
+        self
+    }
+
+    /// Adds a `#define NAME VALUE` to the header prepended to every shader stage.
+    pub fn define(mut self, name: &str, value: &str) -> ProgramBuilder {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the source of `#include`d files.
+    ///
+    /// Each shader stage's source is scanned line by line for a `#include "path"` directive
+    /// (occurrences inside `/* */` or `//` comments are ignored), and every one found is
+    /// replaced with the text returned by `resolver` before the `#version`/`#define` header is
+    /// applied. Includes are expanded recursively: an include cycle is reported as
+    /// `ProgramCreationError::IncludeError`, and a path that was already expanded earlier in
+    /// the same stage is skipped instead of being substituted twice.
+    pub fn include_resolver(mut self, resolver: IncludeSource) -> ProgramBuilder {
+        self.include_resolver = Some(resolver);
+        self
+    }
+
+    /// Compiles and links the stages that were supplied into a `Program`.
+    pub fn build(self, display: &Display) -> Result<Program, ProgramCreationError> {
+        let mut stages = Vec::new();
+
+        match self.vertex_shader {
+            Some(ref src) => stages.push((gl::VERTEX_SHADER, src.clone())),
+            None => ()
+        }
+        match self.tess_control_shader {
+            Some(ref src) => stages.push((gl::TESS_CONTROL_SHADER, src.clone())),
+            None => ()
+        }
+        match self.tess_evaluation_shader {
+            Some(ref src) => stages.push((gl::TESS_EVALUATION_SHADER, src.clone())),
+            None => ()
+        }
+        match self.geometry_shader {
+            Some(ref src) => stages.push((gl::GEOMETRY_SHADER, src.clone())),
+            None => ()
+        }
+        match self.fragment_shader {
+            Some(ref src) => stages.push((gl::FRAGMENT_SHADER, src.clone())),
+            None => ()
+        }
+        match self.compute_shader {
+            Some(ref src) => stages.push((gl::COMPUTE_SHADER, src.clone())),
+            None => ()
+        }
+
+        let defines: Vec<(&str, &str)> = self.defines.iter()
+            .map(|&(ref name, ref value)| (name.as_slice(), value.as_slice())).collect();
+
+        build_program(display, stages, self.version, defines.as_slice(), self.include_resolver)
+    }
+}
+
+/// Builds every requested shader stage and links them together into a `Program`.
+fn build_program(display: &Display, stages: Vec<(gl::types::GLenum, String)>,
+                  version: Option<ShaderVersion>, defines: &[(&str, &str)],
+                  mut include_resolver: Option<IncludeSource>)
+    -> Result<Program, ProgramCreationError>
+{
+    let mut shaders_store = Vec::new();
+    for &(shader_type, ref source_code) in stages.iter() {
+        let preprocessed;
+        let source_code = match include_resolver {
+            Some(ref mut resolver) => {
+                preprocessed = try!(preprocess_includes(resolver, source_code.as_slice()));
+                preprocessed.as_slice()
+            },
+            None => source_code.as_slice(),
+        };
+        let source_code = apply_shader_header(version, defines, source_code);
+        shaders_store.push(try!(build_shader(display, shader_type, source_code.as_slice())));
+    }
+
+    let mut shaders_ids = Vec::new();
+    for sh in shaders_store.iter() {
+        shaders_ids.push(sh.id);
+    }
+
+    let (tx, rx) = channel();
+    display.context.context.exec(proc(ctxt) {
+        unsafe {
+            let id = ctxt.gl.CreateProgram();
+            if id == 0 {
+                tx.send(Err(ProgramCreationError::ProgramCreationFailure));
+                return;
+            }
+
+            // attaching shaders
+            for sh in shaders_ids.iter() {
+                ctxt.gl.AttachShader(id, sh.clone());
+            }
+
+            // so that `get_binary` can retrieve the linked program afterwards
+            ctxt.gl.ProgramParameteri(id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as gl::types::GLint);
+
+            // linking and checking for errors
+            ctxt.gl.LinkProgram(id);
+            {   let mut link_success: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetProgramiv(id, gl::LINK_STATUS, &mut link_success);
+                if link_success == 0 {
+                    use ProgramCreationError::LinkingError;
+
+                    match ctxt.gl.GetError() {
+                        gl::NO_ERROR => (),
+                        gl::INVALID_VALUE => {
+                            tx.send(Err(LinkingError(format!("glLinkProgram triggered \
+                                                              GL_INVALID_VALUE"))));
+                            return;
+                        },
+                        gl::INVALID_OPERATION => {
+                            tx.send(Err(LinkingError(format!("glLinkProgram triggered \
+                                                              GL_INVALID_OPERATION"))));
+                            return;
+                        },
+                        _ => {
+                            tx.send(Err(LinkingError(format!("glLinkProgram triggered an \
+                                                              unknown error"))));
+                            return;
+                        }
+                    };
+
+                    let mut error_log_size: gl::types::GLint = mem::uninitialized();
+                    ctxt.gl.GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut error_log_size);
+
+                    let mut error_log: Vec<u8> = Vec::with_capacity(error_log_size as uint);
+                    ctxt.gl.GetProgramInfoLog(id, error_log_size, &mut error_log_size,
+                        error_log.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+                    error_log.set_len(error_log_size as uint);
+
+                    let msg = String::from_utf8(error_log).unwrap();
+                    tx.send(Err(LinkingError(msg)));
+                    return;
                 }
+            }
+
+            tx.send(Ok(id));
+        }
+    });
+
+    let id = try!(rx.recv());
+
+    let (uniforms, attributes) = reflect_program(display, id);
+
+    Ok(Program {
+        display: display.context.clone(),
+        shaders: shaders_store,
+        id: id,
+        uniforms: uniforms,
+        attributes: attributes,
+    })
+}
+
+/// Reflects the active uniforms and vertex attributes of a linked program.
+fn reflect_program(display: &Display, id: gl::types::GLuint)
+    -> (Arc<HashMap<String, (gl::types::GLint, gl::types::GLenum, gl::types::GLint)>>,
+        Arc<HashMap<String, (gl::types::GLint, gl::types::GLenum, gl::types::GLint)>>)
+{
+    let (tx, rx) = channel();
+    display.context.context.exec(proc(ctxt) {
+        unsafe {
+            // reflecting program uniforms
+            let mut uniforms = HashMap::new();
+
+            let mut active_uniforms: gl::types::GLint = mem::uninitialized();
+            ctxt.gl.GetProgramiv(id, gl::ACTIVE_UNIFORMS, &mut active_uniforms);
+
+            for uniform_id in range(0, active_uniforms) {
+                let mut uniform_name_tmp: Vec<u8> = Vec::with_capacity(64);
+                let mut uniform_name_tmp_len = 63;
+
+                let mut data_type: gl::types::GLenum = mem::uninitialized();
+                let mut data_size: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetActiveUniform(id, uniform_id as gl::types::GLuint, uniform_name_tmp_len,
+                    &mut uniform_name_tmp_len, &mut data_size, &mut data_type,
+                    uniform_name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+                uniform_name_tmp.set_len(uniform_name_tmp_len as uint);
 
-                tx.send(Arc::new(uniforms));
+                let uniform_name = String::from_utf8(uniform_name_tmp).unwrap();
+                let location = ctxt.gl.GetUniformLocation(id, uniform_name.to_c_str().into_inner());
+
+                uniforms.insert(uniform_name, (location, data_type, data_size));
             }
-        });
 
-        Ok(Program {
-            display: display.context.clone(),
-            shaders: shaders_store,
-            id: id,
-            uniforms: rx.recv(),
-        })
-    }
+            // reflecting vertex attributes
+            let mut attributes = HashMap::new();
+
+            let mut active_attributes: gl::types::GLint = mem::uninitialized();
+            ctxt.gl.GetProgramiv(id, gl::ACTIVE_ATTRIBUTES, &mut active_attributes);
+
+            for attribute_id in range(0, active_attributes) {
+                let mut attribute_name_tmp: Vec<u8> = Vec::with_capacity(64);
+                let mut attribute_name_tmp_len = 63;
+
+                let mut data_type: gl::types::GLenum = mem::uninitialized();
+                let mut data_size: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetActiveAttrib(id, attribute_id as gl::types::GLuint, attribute_name_tmp_len,
+                    &mut attribute_name_tmp_len, &mut data_size, &mut data_type,
+                    attribute_name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+                attribute_name_tmp.set_len(attribute_name_tmp_len as uint);
+
+                let attribute_name = String::from_utf8(attribute_name_tmp).unwrap();
+                let location = ctxt.gl.GetAttribLocation(id, attribute_name.to_c_str().into_inner());
+
+                attributes.insert(attribute_name, (location, data_type, data_size));
+            }
+
+            tx.send((Arc::new(uniforms), Arc::new(attributes)));
+        }
+    });
+
+    rx.recv()
 }
 
 impl fmt::Show for Program {
@@ -203,6 +723,12 @@ pub fn get_uniforms_locations(program: &Program) -> Arc<HashMap<String, (gl::typ
     program.uniforms.clone()
 }
 
+pub fn get_attributes_locations(program: &Program) -> Arc<HashMap<String, (gl::types::GLint,
+    gl::types::GLenum, gl::types::GLint)>>
+{
+    program.attributes.clone()
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
         // removing VAOs which contain this program
@@ -239,9 +765,13 @@ fn build_shader<S: ToCStr>(display: &Display, shader_type: gl::types::GLenum, so
     let (tx, rx) = channel();
     display.context.context.exec(proc(ctxt) {
         unsafe {
-            if shader_type == gl::GEOMETRY_SHADER && ctxt.opengl_es {
-                tx.send(Err(ProgramCreationError::ShaderTypeNotSupported));
-                return;
+            match shader_type {
+                gl::GEOMETRY_SHADER | gl::TESS_CONTROL_SHADER | gl::TESS_EVALUATION_SHADER |
+                gl::COMPUTE_SHADER if ctxt.opengl_es => {
+                    tx.send(Err(ProgramCreationError::ShaderTypeNotSupported));
+                    return;
+                },
+                _ => ()
             }
 
             let id = ctxt.gl.CreateShader(shader_type);